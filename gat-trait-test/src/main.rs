@@ -1,6 +1,10 @@
 #![feature(generic_associated_types)]
 #![feature(type_alias_impl_trait)]
 
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+
 fn main() {
     println!("Hello, world!");
 }
@@ -17,4 +21,128 @@ impl Animal for Dog {
     async fn run(&self) {
         todo!()
     }
+}
+
+// `boxed` trades the zero-cost GAT expansion for a `Pin<Box<dyn Future>>`
+// return type, e.g. when the trait needs to stay dyn-compatible on its own
+// (without a `dyn = ...` companion) or callers don't care about the
+// allocation.
+#[gat_trait::gat_trait(boxed)]
+trait Fish {
+    async fn swim(&self);
+}
+
+struct Trout;
+
+#[gat_trait::gat_trait(boxed)]
+impl Fish for Trout {
+    async fn swim(&self) {
+        todo!()
+    }
+}
+
+#[gat_trait::gat_trait]
+trait PinnedAnimal {
+    async fn poll_run(self: Pin<&mut Self>);
+    async fn peek(self: Pin<&Self>);
+    async fn consume_arc(self: Arc<Self>);
+    async fn consume_box(self: Box<Self>);
+
+    // `Rc<Self>` is never `Send`, so this can't join the trait's default
+    // `Send` bound no matter what `Self` is; it must opt out individually.
+    #[gat_trait::not_send]
+    async fn consume_rc(self: Rc<Self>);
+}
+
+struct Cat;
+
+#[gat_trait::gat_trait]
+impl PinnedAnimal for Cat {
+    async fn poll_run(self: Pin<&mut Self>) {
+        todo!()
+    }
+
+    async fn peek(self: Pin<&Self>) {
+        todo!()
+    }
+
+    async fn consume_arc(self: Arc<Self>) {
+        todo!()
+    }
+
+    async fn consume_box(self: Box<Self>) {
+        todo!()
+    }
+
+    #[gat_trait::not_send]
+    async fn consume_rc(self: Rc<Self>) {
+        todo!()
+    }
+}
+
+// The trait already declares lifetimes named `'gat_trait` and `'life0`, the
+// same names `#[gat_trait]` would otherwise synthesize; the macro must pick
+// different names rather than shadowing these. `&'gat_trait self` also
+// relies on `has_self` recognizing a named-lifetime shorthand receiver, since
+// the resulting `Self: 'synthetic` bound is what makes the GAT's own
+// `where Self: 'a` well-formedness requirement provable at the call site.
+#[gat_trait::gat_trait]
+trait Named<'gat_trait, 'life0> {
+    async fn borrow_both(&'gat_trait self, other: &'life0 str);
+}
+
+struct Named0;
+
+#[gat_trait::gat_trait]
+impl<'gat_trait, 'life0> Named<'gat_trait, 'life0> for Named0 {
+    async fn borrow_both(&'gat_trait self, other: &'life0 str) {
+        let _ = other;
+    }
+}
+
+// `run` stays `Send`; `run_on_local` opts out because it touches an `Rc`.
+//
+// Both methods stay abstract here: a trait-level default body can't work
+// under this expansion, since its return type is the trait's own abstract
+// `Self::XResultFuture<'a>` GAT, which (unlike the impl-side TAIT) has no
+// defining scope for a concrete body to satisfy.
+#[gat_trait::gat_trait]
+trait Executor {
+    async fn run(&self);
+
+    #[gat_trait::not_send]
+    async fn run_on_local(&self, handle: std::rc::Rc<()>);
+}
+
+struct LocalExecutor;
+
+#[gat_trait::gat_trait]
+impl Executor for LocalExecutor {
+    async fn run(&self) {}
+
+    #[gat_trait::not_send]
+    async fn run_on_local(&self, handle: std::rc::Rc<()>) {
+        let _ = handle;
+    }
+}
+
+// `Bird` keeps static dispatch through `#[gat_trait]`'s GAT expansion, while
+// `DynBird` is an object-safe sibling that lets callers hold `Box<dyn
+// DynBird>` when dynamic dispatch is unavoidable.
+#[gat_trait::gat_trait(dyn = DynBird)]
+trait Bird {
+    async fn fly(&self);
+}
+
+struct Sparrow;
+
+#[gat_trait::gat_trait]
+impl Bird for Sparrow {
+    async fn fly(&self) {
+        todo!()
+    }
+}
+
+fn use_boxed_bird(bird: Box<dyn DynBird>) {
+    let _ = bird;
 }
\ No newline at end of file
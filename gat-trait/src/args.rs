@@ -1,14 +1,17 @@
 use proc_macro2::Span;
 use syn::parse::{Error, Parse, ParseStream, Result};
-use syn::Token;
+use syn::{Ident, Token};
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct Args {
     pub local: bool,
+    pub boxed: bool,
+    pub dyn_trait: Option<Ident>,
 }
 
 mod kw {
     syn::custom_keyword!(Send);
+    syn::custom_keyword!(boxed);
 }
 
 impl Parse for Args {
@@ -21,16 +24,35 @@ impl Parse for Args {
 }
 
 fn try_parse(input: ParseStream) -> Result<Args> {
-    if input.peek(Token![?]) {
+    let mut boxed = false;
+    let mut dyn_trait = None;
+    if input.peek(kw::boxed) {
+        input.parse::<kw::boxed>()?;
+        boxed = true;
+    } else if input.peek(Token![dyn]) {
+        input.parse::<Token![dyn]>()?;
+        input.parse::<Token![=]>()?;
+        dyn_trait = Some(input.parse::<Ident>()?);
+    }
+    if (boxed || dyn_trait.is_some()) && input.peek(Token![,]) {
+        input.parse::<Token![,]>()?;
+    }
+    let local = if input.peek(Token![?]) {
         input.parse::<Token![?]>()?;
         input.parse::<kw::Send>()?;
-        Ok(Args { local: true })
+        true
     } else {
-        Ok(Args { local: false })
-    }
+        false
+    };
+    Ok(Args {
+        local,
+        boxed,
+        dyn_trait,
+    })
 }
 
 fn error() -> Error {
-    let msg = "expected #[gat_trait] or #[gat_trait(?Send)]";
+    let msg = "expected #[gat_trait], #[gat_trait(?Send)], #[gat_trait(boxed)], \
+               #[gat_trait(boxed, ?Send)], or #[gat_trait(dyn = DynTrait)]";
     Error::new(Span::call_site(), msg)
 }
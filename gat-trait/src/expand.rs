@@ -1,4 +1,6 @@
-use crate::lifetime::{AddLifetimeToImplTrait, CollectLifetimes};
+use crate::lifetime::{
+    AddLifetimeToImplTrait, CollectLifetimeNames, CollectLifetimes, unique_lifetime_name,
+};
 use crate::parse::Item;
 use crate::receiver::{has_self_in_block, has_self_in_sig, mut_pat, ReplaceSelf};
 use heck::ToUpperCamelCase;
@@ -7,14 +9,37 @@ use quote::{format_ident, quote, quote_spanned, ToTokens};
 use std::collections::BTreeSet as Set;
 use std::mem;
 use syn::punctuated::Punctuated;
+use syn::visit::Visit;
 use syn::visit_mut::{self, VisitMut};
 use syn::{
-    parse_quote, parse_quote_spanned, Attribute, Block, FnArg, GenericParam, Generics, Ident,
-    ImplItem, Lifetime, LifetimeDef, Pat, PatIdent, Receiver, ReturnType, Signature, Stmt, Token,
-    TraitItem, Type, TypeParamBound, TypePath, WhereClause,
+    parse_quote, parse_quote_spanned, Attribute, Block, FnArg, GenericArgument, GenericParam,
+    Generics, Ident, ImplItem, Lifetime, LifetimeDef, Pat, PatIdent, PathArguments, Receiver,
+    ReturnType, Signature, Stmt, Token, TraitItem, Type, TypeParamBound, TypePath, WhereClause,
 };
 use syn::parse_quote::ParseQuote;
 
+/// The synthetic lifetime names threaded through a single `expand` call,
+/// chosen up front so they never collide with a lifetime the user's trait or
+/// impl already declares.
+struct SyntheticLifetimes {
+    gat_trait: Lifetime,
+    life: String,
+    impl_: String,
+}
+
+impl SyntheticLifetimes {
+    fn new(reserved: &Set<String>) -> Self {
+        SyntheticLifetimes {
+            gat_trait: Lifetime::new(
+                &format!("'{}", unique_lifetime_name("gat_trait", reserved)),
+                Span::call_site(),
+            ),
+            life: format!("'{}", unique_lifetime_name("life", reserved)),
+            impl_: format!("'{}", unique_lifetime_name("impl", reserved)),
+        }
+    }
+}
+
 impl ToTokens for Item {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         match self {
@@ -55,16 +80,40 @@ impl Context<'_> {
 
 type SuperTraits = Punctuated<TypeParamBound, Token![+]>;
 
-pub fn expand(input: &mut Item, is_local: bool) {
+pub fn expand(input: &mut Item, is_local: bool, boxed: bool, dyn_trait: Option<Ident>) -> TokenStream {
     match input {
         Item::Trait(input) => {
+            let mut names = CollectLifetimeNames::default();
+            names.visit_generics(&input.generics);
+            for inner in &input.items {
+                if let TraitItem::Method(method) = inner {
+                    names.visit_signature(&method.sig);
+                }
+            }
+            let lifetimes = SyntheticLifetimes::new(&names.0);
+
             let context = Context::Trait {
                 generics: &input.generics,
                 super_traits: &input.supertraits,
             };
+
+            let dyn_companion = dyn_trait.map(|dyn_name| {
+                build_dyn_companion(
+                    &input.vis,
+                    &input.ident,
+                    &dyn_name,
+                    &input.generics,
+                    &input.items,
+                    context,
+                    is_local,
+                    &lifetimes,
+                )
+            });
+
             let mut items = Vec::new();
             for inner in &mut input.items {
                 if let TraitItem::Method(method) = inner {
+                    let method_is_local = is_local || take_not_send(&mut method.attrs);
                     let sig = &mut method.sig;
                     if sig.asyncness.is_some() {
                         let block = &mut method.default;
@@ -72,26 +121,49 @@ pub fn expand(input: &mut Item, is_local: bool) {
                         method.attrs.push(parse_quote!(#[must_use]));
                         if let Some(block) = block {
                             has_self |= has_self_in_block(block);
-                            transform_block(context, sig, block);
+                            transform_block(context, sig, block, boxed);
                             method.attrs.push(lint_suppress_with_body());
                         } else {
                             method.attrs.push(lint_suppress_without_body());
                         }
                         let has_default = method.default.is_some();
-                        items.push(transform_sig(context, sig, has_self, has_default, is_local));
+                        if let Some(trait_item_type) = transform_sig(
+                            context,
+                            sig,
+                            has_self,
+                            has_default,
+                            method_is_local,
+                            boxed,
+                            &lifetimes,
+                        ) {
+                            items.push(trait_item_type);
+                        }
                     }
                 }
             }
             for trait_item_type in items {
                 input.items.push(TraitItem::Type(trait_item_type));
             }
+
+            dyn_companion.unwrap_or_default()
         }
         Item::Impl(input) => {
-            let mut lifetimes = CollectLifetimes::new("'impl", input.impl_token.span);
-            lifetimes.visit_type_mut(&mut *input.self_ty);
-            lifetimes.visit_path_mut(&mut input.trait_.as_mut().unwrap().1);
+            let mut names = CollectLifetimeNames::default();
+            names.visit_generics(&input.generics);
+            names.visit_type(&input.self_ty);
+            names.visit_path(&input.trait_.as_ref().unwrap().1);
+            for inner in &input.items {
+                if let ImplItem::Method(method) = inner {
+                    names.visit_signature(&method.sig);
+                }
+            }
+            let lifetimes = SyntheticLifetimes::new(&names.0);
+
+            let mut collected = CollectLifetimes::new(lifetimes.impl_.clone(), input.impl_token.span);
+            collected.visit_type_mut(&mut *input.self_ty);
+            collected.visit_path_mut(&mut input.trait_.as_mut().unwrap().1);
             let params = &input.generics.params;
-            let elided = lifetimes.elided;
+            let elided = collected.elided;
             input.generics.params = parse_quote!(#(#elided,)* #params);
 
             let mut associated_type_impl_traits = Set::new();
@@ -110,12 +182,23 @@ pub fn expand(input: &mut Item, is_local: bool) {
             let mut items = Vec::new();
             for inner in &mut input.items {
                 if let ImplItem::Method(method) = inner {
+                    let method_is_local = is_local || take_not_send(&mut method.attrs);
                     let sig = &mut method.sig;
                     if sig.asyncness.is_some() {
                         let block = &mut method.block;
                         let has_self = has_self_in_sig(sig) || has_self_in_block(block);
-                        transform_block(context, sig, block);
-                        items.push(transform_sig(context, sig, has_self, false, is_local));
+                        transform_block(context, sig, block, boxed);
+                        if let Some(impl_item_type) = transform_sig(
+                            context,
+                            sig,
+                            has_self,
+                            false,
+                            method_is_local,
+                            boxed,
+                            &lifetimes,
+                        ) {
+                            items.push(impl_item_type);
+                        }
                         method.attrs.push(lint_suppress_with_body());
                     }
                 }
@@ -123,10 +206,141 @@ pub fn expand(input: &mut Item, is_local: bool) {
             for trait_item_type in items {
                 input.items.push(ImplItem::Type(trait_item_type));
             }
+
+            TokenStream::new()
         }
     }
 }
 
+// `#[gat_trait(dyn = DynAnimal)]` keeps the zero-cost GAT expansion of the
+// trait itself and additionally emits an object-safe sibling trait whose
+// methods return `Pin<Box<dyn Future<...>>>`, plus a blanket impl bridging
+// any `Animal + Send + Sync` into `DynAnimal` so callers who need `Box<dyn
+// DynAnimal>` don't have to give up static dispatch through `Animal`. Methods
+// generic over a type parameter can't be part of an object-safe trait, so
+// they're rejected with a `compile_error!` in `DynAnimal` rather than being
+// forwarded.
+#[allow(clippy::too_many_arguments)]
+fn build_dyn_companion(
+    vis: &syn::Visibility,
+    trait_ident: &Ident,
+    dyn_name: &Ident,
+    generics: &Generics,
+    items: &[TraitItem],
+    context: Context,
+    is_local: bool,
+    lifetimes: &SyntheticLifetimes,
+) -> TokenStream {
+    let mut dyn_methods = Vec::new();
+    let mut forwards = Vec::new();
+
+    for item in items {
+        if let TraitItem::Method(method) = item {
+            if method.sig.asyncness.is_none() {
+                continue;
+            }
+            let has_type_param = method
+                .sig
+                .generics
+                .params
+                .iter()
+                .any(|param| matches!(param, GenericParam::Type(_)));
+            if has_type_param {
+                // A method generic over a type can't appear on an object-safe
+                // trait at all, so `DynX` can't forward it; fail loudly here
+                // instead of leaving the user to puzzle out a vtable error.
+                let msg = format!(
+                    "`#[gat_trait(dyn = {})]` cannot forward `{}`: a method generic \
+                     over a type parameter is not object-safe",
+                    dyn_name, method.sig.ident,
+                );
+                let span = method.sig.ident.span();
+                dyn_methods.push(quote_spanned!(span=> compile_error!(#msg);));
+                continue;
+            }
+            let method_is_local = is_local || has_not_send(&method.attrs);
+            let has_default = method.default.is_some();
+            let mut sig = method.sig.clone();
+            let has_self = has_self_in_sig(&mut sig);
+            let _: Option<syn::TraitItemType> = transform_sig(
+                context,
+                &mut sig,
+                has_self,
+                has_default,
+                method_is_local,
+                true,
+                lifetimes,
+            );
+
+            let ident = sig.ident.clone();
+            let call_args: Vec<Ident> = sig
+                .inputs
+                .iter()
+                .filter_map(|arg| match arg {
+                    FnArg::Typed(arg) => match arg.pat.as_ref() {
+                        Pat::Ident(pat) => Some(pat.ident.clone()),
+                        _ => None,
+                    },
+                    FnArg::Receiver(_) => None,
+                })
+                .collect();
+
+            dyn_methods.push(quote!(#sig;));
+            forwards.push((sig, ident, call_args));
+        }
+    }
+
+    let mut blanket_generics = generics.clone();
+    blanket_generics
+        .params
+        .push(parse_quote!(__GatTraitDynFor));
+    let (impl_generics, _, _) = blanket_generics.split_for_impl();
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+    let extra_predicates = where_clause.map(|clause| &clause.predicates);
+    let forwards = forwards.into_iter().map(|(sig, ident, call_args)| {
+        quote! {
+            #sig {
+                ::std::boxed::Box::pin(<Self as #trait_ident #ty_generics>::#ident(self, #(#call_args),*))
+            }
+        }
+    });
+
+    quote! {
+        #vis trait #dyn_name #generics {
+            #(#dyn_methods)*
+        }
+
+        impl #impl_generics #dyn_name #ty_generics for __GatTraitDynFor
+        where
+            __GatTraitDynFor: #trait_ident #ty_generics + ::core::marker::Send + ::core::marker::Sync,
+            #extra_predicates
+        {
+            #(#forwards)*
+        }
+    }
+}
+
+// Detects and removes a per-method `#[gat_trait::not_send]` (or bare
+// `#[not_send]`, for callers who `use gat_trait::not_send;`) marker, which
+// locally flips a single method of an otherwise-`Send` trait to `?Send`
+// behavior without requiring the whole trait to opt out.
+fn is_not_send_attr(attr: &Attribute) -> bool {
+    attr.path.is_ident("not_send")
+        || (attr.path.segments.len() == 2
+            && attr.path.segments[0].ident == "gat_trait"
+            && attr.path.segments[1].ident == "not_send")
+}
+
+fn has_not_send(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(is_not_send_attr)
+}
+
+fn take_not_send(attrs: &mut Vec<Attribute>) -> bool {
+    let found = has_not_send(attrs);
+    attrs.retain(|attr| !is_not_send_attr(attr));
+    found
+}
+
 fn lint_suppress_with_body() -> Attribute {
     parse_quote! {
         #[allow(
@@ -168,16 +382,19 @@ fn transform_sig<T: ParseQuote>(
     has_self: bool,
     has_default: bool,
     is_local: bool,
-) -> T {
+    boxed: bool,
+    synth: &SyntheticLifetimes,
+) -> Option<T> {
     let default_span = sig.asyncness.take().unwrap().span;
     sig.fn_token.span = default_span;
+    let gat_trait = &synth.gat_trait;
 
     let (ret_arrow, ret) = match &sig.output {
         ReturnType::Default => (Token![->](default_span), quote_spanned!(default_span=> ())),
         ReturnType::Type(arrow, ret) => (*arrow, quote!(#ret)),
     };
 
-    let mut lifetimes = CollectLifetimes::new("'life", default_span);
+    let mut lifetimes = CollectLifetimes::new(synth.life.clone(), default_span);
     for arg in sig.inputs.iter_mut() {
         match arg {
             FnArg::Receiver(arg) => lifetimes.visit_receiver_mut(arg),
@@ -193,10 +410,10 @@ fn transform_sig<T: ParseQuote>(
                     Some(colon_token) => colon_token.span,
                     None => param_name.span(),
                 };
-                let bounds = mem::replace(&mut param.bounds, Punctuated::new());
+                let bounds = mem::take(&mut param.bounds);
                 where_clause_or_default(&mut sig.generics.where_clause)
                     .predicates
-                    .push(parse_quote_spanned!(span=> #param_name: 'gat_trait + #bounds));
+                    .push(parse_quote_spanned!(span=> #param_name: #gat_trait + #bounds));
             }
             GenericParam::Lifetime(param) => {
                 let param_name = &param.lifetime;
@@ -204,10 +421,10 @@ fn transform_sig<T: ParseQuote>(
                     Some(colon_token) => colon_token.span,
                     None => param_name.span(),
                 };
-                let bounds = mem::replace(&mut param.bounds, Punctuated::new());
+                let bounds = mem::take(&mut param.bounds);
                 where_clause_or_default(&mut sig.generics.where_clause)
                     .predicates
-                    .push(parse_quote_spanned!(span=> #param: 'gat_trait + #bounds));
+                    .push(parse_quote_spanned!(span=> #param: #gat_trait + #bounds));
             }
             GenericParam::Const(_) => {}
         }
@@ -218,7 +435,7 @@ fn transform_sig<T: ParseQuote>(
         let span = param.span();
         where_clause_or_default(&mut sig.generics.where_clause)
             .predicates
-            .push(parse_quote_spanned!(span=> #param: 'gat_trait));
+            .push(parse_quote_spanned!(span=> #param: #gat_trait));
     }
 
     if sig.generics.lt_token.is_none() {
@@ -232,44 +449,75 @@ fn transform_sig<T: ParseQuote>(
         sig.generics.params.push(parse_quote!(#elided));
         where_clause_or_default(&mut sig.generics.where_clause)
             .predicates
-            .push(parse_quote_spanned!(elided.span()=> #elided: 'gat_trait));
+            .push(parse_quote_spanned!(elided.span()=> #elided: #gat_trait));
     }
 
     sig.generics
         .params
-        .push(parse_quote_spanned!(default_span=> 'gat_trait));
+        .push(parse_quote_spanned!(default_span=> #gat_trait));
 
     if has_self {
-        let bound = match sig.inputs.iter().next() {
+        let self_ty = match sig.inputs.iter().next() {
+            Some(FnArg::Typed(arg)) => match arg.pat.as_ref() {
+                Pat::Ident(pat) if pat.ident == "self" => Some(arg.ty.as_ref()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let by_shared_ref = matches!(
+            sig.inputs.iter().next(),
             Some(FnArg::Receiver(Receiver {
                 reference: Some(_),
                 mutability: None,
                 ..
-            })) => Ident::new("Sync", default_span),
-            Some(FnArg::Typed(arg))
-                if match (arg.pat.as_ref(), arg.ty.as_ref()) {
-                    (Pat::Ident(pat), Type::Reference(ty)) => {
-                        pat.ident == "self" && ty.mutability.is_none()
-                    }
-                    _ => false,
-                } =>
-            {
-                Ident::new("Sync", default_span)
-            }
-            _ => Ident::new("Send", default_span),
-        };
-
-        let assume_bound = match context {
-            Context::Trait { super_traits, .. } => !has_default || has_bound(super_traits, &bound),
-            Context::Impl { .. } => true,
+            }))
+        );
+
+        let push_marker_bound = |where_clause: &mut WhereClause, markers: &[&str]| {
+            let markers: Vec<Ident> = markers
+                .iter()
+                .map(|marker| Ident::new(marker, default_span))
+                .collect();
+            let assume_bound = match context {
+                Context::Trait { super_traits, .. } => {
+                    !has_default || markers.iter().all(|marker| has_bound(super_traits, marker))
+                }
+                Context::Impl { .. } => true,
+            };
+            where_clause.predicates.push(if assume_bound || is_local {
+                parse_quote_spanned!(default_span=> Self: #gat_trait)
+            } else {
+                let bounds = markers
+                    .iter()
+                    .map(|marker| quote_spanned!(default_span=> ::core::marker::#marker));
+                parse_quote_spanned!(default_span=> Self: #(#bounds +)* #gat_trait)
+            });
         };
 
         let where_clause = where_clause_or_default(&mut sig.generics.where_clause);
-        where_clause.predicates.push(if assume_bound || is_local {
-            parse_quote_spanned!(default_span=> Self: 'gat_trait)
+        if by_shared_ref || self_ty.is_some_and(receiver_type_is_shared) {
+            push_marker_bound(where_clause, &["Sync"]);
+        } else if let Some(ty) = self_ty.filter(|ty| receiver_smart_pointer(ty) == Some("Rc")) {
+            if !is_local {
+                // `Rc<Self>` is never `Send` no matter what `Self` is, because the
+                // generated future captures the receiver as-is; the explicit
+                // `Rc<Self>: Send` bound makes the compiler report this on the
+                // method's signature instead of deep inside the expansion.
+                // `#[gat_trait::not_send]` on the method drops this bound.
+                let ty = ty.clone();
+                where_clause
+                    .predicates
+                    .push(parse_quote_spanned!(default_span=> #ty: ::core::marker::Send));
+            }
+            where_clause
+                .predicates
+                .push(parse_quote_spanned!(default_span=> Self: #gat_trait));
+        } else if self_ty.is_some_and(|ty| receiver_smart_pointer(ty) == Some("Arc")) {
+            // `Arc<Self>: Send` needs `Self: Send + Sync`, not just `Self: Send`.
+            push_marker_bound(where_clause, &["Send", "Sync"]);
         } else {
-            parse_quote_spanned!(default_span=> Self: ::core::marker::#bound + 'gat_trait)
-        });
+            push_marker_bound(where_clause, &["Send"]);
+        }
     }
 
     for (i, arg) in sig.inputs.iter_mut().enumerate() {
@@ -287,23 +535,31 @@ fn transform_sig<T: ParseQuote>(
                     let m = mut_pat(&mut arg.pat);
                     arg.pat = parse_quote!(#m #positional);
                 }
-                AddLifetimeToImplTrait.visit_type_mut(&mut arg.ty);
+                AddLifetimeToImplTrait(gat_trait).visit_type_mut(&mut arg.ty);
             }
         }
     }
 
-    let bound = quote_spanned!(default_span=> 'gat_trait);
+    let bound = quote_spanned!(default_span=> #gat_trait);
     let bounds = if is_local {
         bound.clone()
     } else {
-        quote_spanned!(default_span=> ::core::marker::Send + 'gat_trait)
+        quote_spanned!(default_span=> ::core::marker::Send + #gat_trait)
     };
+
+    if boxed {
+        sig.output = parse_quote_spanned! {default_span=>
+            #ret_arrow ::core::pin::Pin<::std::boxed::Box<dyn ::core::future::Future<Output = #ret> + #bounds>>
+        };
+        return None;
+    }
+
     let ret_fut_name = upper_camel_case_ret_future(&sig.ident);
     sig.output = parse_quote_spanned! {default_span=>
         #ret_arrow Self::#ret_fut_name<#bound>
     };
 
-    match context {
+    Some(match context {
         Context::Trait {..} => parse_quote!(
             type #ret_fut_name<#bound>: ::core::future::Future<Output = #ret> + #bounds
             where
@@ -312,7 +568,7 @@ fn transform_sig<T: ParseQuote>(
         Context::Impl {..} => parse_quote!(
             type #ret_fut_name<#bound> = impl ::core::future::Future<Output = #ret> + #bounds;
         )
-    }
+    })
 }
 
 // Input:
@@ -332,7 +588,7 @@ fn transform_sig<T: ParseQuote>(
 //
 //         ___ret
 //     }
-fn transform_block(context: Context, sig: &mut Signature, block: &mut Block) {
+fn transform_block(context: Context, sig: &mut Signature, block: &mut Block, boxed: bool) {
     if let Some(Stmt::Item(syn::Item::Verbatim(item))) = block.stmts.first() {
         if block.stmts.len() == 1 && item.to_string() == ";" {
             return;
@@ -410,9 +666,15 @@ fn transform_block(context: Context, sig: &mut Signature, block: &mut Block) {
             }
         }
     };
-    let async_stmt = quote_spanned!(block.brace_token.span=>
-        async move { #let_ret }
-    );
+    let async_stmt = if boxed {
+        quote_spanned!(block.brace_token.span=>
+            ::std::boxed::Box::pin(async move { #let_ret })
+        )
+    } else {
+        quote_spanned!(block.brace_token.span=>
+            async move { #let_ret }
+        )
+    };
     block.stmts = parse_quote!(#async_stmt);
 }
 
@@ -423,6 +685,42 @@ fn positional_arg(i: usize, pat: &Pat) -> Ident {
     format_ident!("__arg{}", i, span = span)
 }
 
+// `self: &Self` and `self: Pin<&Self>` only ever hand out shared access, the
+// same as `&self`, so they infer `Sync` like the short receiver form does.
+// Everything else typed (`self: Pin<&mut Self>`, `self: Arc<Self>`,
+// `self: Box<Self>`, `self: Rc<Self>`, bare `self: Self`) requires unique
+// access to the receiver and so infers `Send` instead, except for the smart
+// pointers handled separately in `receiver_smart_pointer`.
+fn receiver_type_is_shared(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(ty) => ty.mutability.is_none(),
+        Type::Path(TypePath { qself: None, path }) => match path.segments.last() {
+            Some(seg) if seg.ident == "Pin" => match &seg.arguments {
+                PathArguments::AngleBracketed(args) => args.args.iter().any(|arg| {
+                    matches!(arg, GenericArgument::Type(ty) if receiver_type_is_shared(ty))
+                }),
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Identifies `self: Rc<Self>` / `self: Arc<Self>` receivers, whose `Send`
+/// requirements don't follow the usual "unique access needs `Self: Send`"
+/// rule: the future captures the smart pointer itself, not `Self`.
+fn receiver_smart_pointer(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => match path.segments.last() {
+            Some(seg) if seg.ident == "Rc" => Some("Rc"),
+            Some(seg) if seg.ident == "Arc" => Some("Arc"),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 fn has_bound(super_traits: &SuperTraits, marker: &Ident) -> bool {
     for bound in super_traits {
         if let TypeParamBound::Trait(bound) = bound {
@@ -483,7 +781,7 @@ fn where_clause_or_default(clause: &mut Option<WhereClause>) -> &mut WhereClause
 }
 
 fn upper_camel_case_ret_future(func: &Ident) -> Ident {
-    let fname = format!("{}_result_future", func.to_string());
+    let fname = format!("{}_result_future", func);
     let fname = fname.to_upper_camel_case();
     Ident::new(&fname, Span::call_site())
 }
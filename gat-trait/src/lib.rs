@@ -29,6 +29,6 @@ use syn::parse_macro_input;
 pub fn gat_trait(args: TokenStream, input: TokenStream) -> TokenStream {
     let args = parse_macro_input!(args as Args);
     let mut item = parse_macro_input!(input as Item);
-    expand(&mut item, args.local);
-    TokenStream::from(quote!(#item))
+    let dyn_companion = expand(&mut item, args.local, args.boxed, args.dyn_trait);
+    TokenStream::from(quote!(#item #dyn_companion))
 }
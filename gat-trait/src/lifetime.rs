@@ -0,0 +1,103 @@
+use proc_macro2::Span;
+use std::collections::BTreeSet as Set;
+use syn::visit::{self, Visit};
+use syn::visit_mut::{self, VisitMut};
+use syn::{GenericArgument, Lifetime, Receiver, Type, TypeReference};
+
+pub struct CollectLifetimes {
+    pub elided: Vec<Lifetime>,
+    pub explicit: Vec<Lifetime>,
+    name: String,
+    default_span: Span,
+}
+
+impl CollectLifetimes {
+    pub fn new(name: impl Into<String>, default_span: Span) -> Self {
+        CollectLifetimes {
+            elided: Vec::new(),
+            explicit: Vec::new(),
+            name: name.into(),
+            default_span,
+        }
+    }
+
+    fn visit_opt_lifetime(&mut self, lifetime: &mut Option<Lifetime>) {
+        match lifetime {
+            None => *lifetime = Some(self.next_lifetime()),
+            Some(lifetime) => self.visit_lifetime(lifetime),
+        }
+    }
+
+    fn visit_lifetime(&mut self, lifetime: &mut Lifetime) {
+        if lifetime.ident == "_" {
+            *lifetime = self.next_lifetime();
+        } else {
+            self.explicit.push(lifetime.clone());
+        }
+    }
+
+    fn next_lifetime(&mut self) -> Lifetime {
+        let name = format!("{}{}", self.name, self.elided.len());
+        let life = Lifetime::new(&name, self.default_span);
+        self.elided.push(life.clone());
+        life
+    }
+}
+
+impl VisitMut for CollectLifetimes {
+    fn visit_receiver_mut(&mut self, arg: &mut Receiver) {
+        if let Some((_, lifetime)) = &mut arg.reference {
+            self.visit_opt_lifetime(lifetime);
+        }
+    }
+
+    fn visit_type_reference_mut(&mut self, ty: &mut TypeReference) {
+        self.visit_opt_lifetime(&mut ty.lifetime);
+        visit_mut::visit_type_reference_mut(self, ty);
+    }
+
+    fn visit_generic_argument_mut(&mut self, gen: &mut GenericArgument) {
+        if let GenericArgument::Lifetime(lifetime) = gen {
+            self.visit_lifetime(lifetime);
+        }
+        visit_mut::visit_generic_argument_mut(self, gen);
+    }
+}
+
+// Replace `impl Trait` in argument position with `impl Trait + 'gat_trait` so
+// that borrowed arguments captured by the generated future outlive it.
+pub struct AddLifetimeToImplTrait<'a>(pub &'a Lifetime);
+
+impl VisitMut for AddLifetimeToImplTrait<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Type::ImplTrait(ty) = ty {
+            let gat_trait = self.0;
+            ty.bounds.push(syn::parse_quote!(#gat_trait));
+        }
+        visit_mut::visit_type_mut(self, ty);
+    }
+}
+
+/// Visitor that records the identifier (without the leading `'`) of every
+/// lifetime appearing in the visited syntax, so synthetic lifetime names can
+/// be chosen to avoid colliding with lifetimes the user already declared.
+#[derive(Default)]
+pub struct CollectLifetimeNames(pub Set<String>);
+
+impl<'ast> Visit<'ast> for CollectLifetimeNames {
+    fn visit_lifetime(&mut self, lifetime: &'ast Lifetime) {
+        self.0.insert(lifetime.ident.to_string());
+        visit::visit_lifetime(self, lifetime);
+    }
+}
+
+/// Appends underscores to `base` until no lifetime in `reserved` could
+/// possibly collide with a name derived from it, mirroring pin-project's
+/// `determine_lifetime_name` approach to synthetic lifetime hygiene.
+pub fn unique_lifetime_name(base: &str, reserved: &Set<String>) -> String {
+    let mut candidate = base.to_string();
+    while reserved.iter().any(|name| name.starts_with(candidate.as_str())) {
+        candidate.push('_');
+    }
+    candidate
+}
@@ -0,0 +1,21 @@
+use syn::parse::{Error, Parse, ParseStream, Result};
+use syn::{ItemImpl, ItemTrait};
+
+pub enum Item {
+    Trait(ItemTrait),
+    Impl(ItemImpl),
+}
+
+impl Parse for Item {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let item: syn::Item = input.parse()?;
+        match item {
+            syn::Item::Trait(item) => Ok(Item::Trait(item)),
+            syn::Item::Impl(item) => Ok(Item::Impl(item)),
+            _ => Err(Error::new_spanned(
+                item,
+                "expected trait or impl block",
+            )),
+        }
+    }
+}
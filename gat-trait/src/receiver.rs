@@ -0,0 +1,75 @@
+use proc_macro2::Span;
+use syn::visit_mut::{self, VisitMut};
+use syn::{Block, Expr, ExprPath, Pat, PatIdent, Path, Receiver, Signature, Token};
+
+pub fn has_self_in_sig(sig: &mut Signature) -> bool {
+    let mut visitor = HasSelf(false);
+    visitor.visit_signature_mut(sig);
+    visitor.0
+}
+
+pub fn has_self_in_block(block: &mut Block) -> bool {
+    let mut visitor = HasSelf(false);
+    visitor.visit_block_mut(block);
+    visitor.0
+}
+
+pub fn mut_pat(pat: &mut Pat) -> Option<Token![mut]> {
+    let mutability = match pat {
+        Pat::Ident(pat) => &mut pat.mutability,
+        _ => return None,
+    };
+    mutability.take()
+}
+
+struct HasSelf(bool);
+
+impl VisitMut for HasSelf {
+    fn visit_ident_mut(&mut self, ident: &mut proc_macro2::Ident) {
+        if ident == "self" || ident == "Self" {
+            self.0 = true;
+        }
+    }
+
+    fn visit_path_mut(&mut self, path: &mut Path) {
+        if path.leading_colon.is_none() && path.segments.first().is_some_and(|seg| seg.ident == "Self") {
+            self.0 = true;
+        }
+        visit_mut::visit_path_mut(self, path);
+    }
+
+    // `&self`/`&mut self`/bare `self` parse as a `Receiver`, not as an `Ident`
+    // or `Path`, so without this override shorthand receivers never set the
+    // flag above.
+    fn visit_receiver_mut(&mut self, receiver: &mut Receiver) {
+        self.0 = true;
+        visit_mut::visit_receiver_mut(self, receiver);
+    }
+}
+
+// Rewrites `self` expressions in the method body to refer to the `__self`
+// binding introduced by `transform_block`.
+pub struct ReplaceSelf(pub Span);
+
+impl VisitMut for ReplaceSelf {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let Expr::Path(ExprPath { qself: None, path, .. }) = expr {
+            if path.is_ident("self") {
+                let mut ident = path.segments[0].ident.clone();
+                ident.set_span(self.0);
+                *path = Path::from(syn::Ident::new("__self", ident.span()));
+                return;
+            }
+        }
+        visit_mut::visit_expr_mut(self, expr);
+    }
+
+    fn visit_pat_mut(&mut self, pat: &mut Pat) {
+        if let Pat::Ident(PatIdent { ident, .. }) = pat {
+            if ident == "self" {
+                return;
+            }
+        }
+        visit_mut::visit_pat_mut(self, pat);
+    }
+}